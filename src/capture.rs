@@ -0,0 +1,187 @@
+//! In-memory capture of log messages sent during tests, so downstream
+//! crates can assert on what their NIFs logged without a live BEAM.
+use super::level::LogLevel;
+use super::message::Log;
+use std::cell::RefCell;
+
+/// A captured log message, rendered to owned, inspectable data.
+#[derive(Debug, Clone)]
+pub struct CapturedLog {
+    /// the log level the message was sent at
+    pub level: LogLevel,
+    /// format string for the message, as passed to `Log::new`
+    pub format: String,
+    /// the message's arguments, each rendered via `Debug`
+    pub args: Vec<String>,
+    /// the message's metadata pairs, each value rendered via `Debug`
+    pub metadata: Vec<(String, String)>,
+}
+
+thread_local! {
+    /// Thread-local capture buffer. Each thread gets its own, matching
+    /// `Log::send`'s existing thread-local `ENV`/`PANIC_INFO` handling.
+    static CAPTURED: RefCell<Vec<CapturedLog>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Render and stash `log` in the capture buffer. Called from `Log::send`'s
+/// `testing` implementation in place of shipping the message to Elixir.
+pub(crate) fn capture(log: &Log) {
+    use rustler::OwnedEnv;
+
+    let (args, metadata) = OwnedEnv::new().run(|env| {
+        let args: Vec<String> = log
+            .args
+            .iter()
+            .map(|arg| format!("{:?}", arg.encode(env)))
+            .collect();
+        let metadata: Vec<(String, String)> = log
+            .metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), format!("{:?}", value.encode(env))))
+            .collect();
+        (args, metadata)
+    });
+
+    CAPTURED.with(|captured| {
+        captured.borrow_mut().push(CapturedLog {
+            level: log.level,
+            format: log.format.clone(),
+            args,
+            metadata,
+        });
+    });
+}
+
+/// Returns a snapshot of every message sent (via `Log::send`) on this thread
+/// since the capture buffer was last cleared.
+pub fn captured_logs() -> Vec<CapturedLog> {
+    CAPTURED.with(|captured| captured.borrow().clone())
+}
+
+/// Clear the capture buffer for this thread.
+pub fn clear_captured() {
+    CAPTURED.with(|captured| captured.borrow_mut().clear());
+}
+
+/// Assert that some captured message at `level` contains `substring` in its
+/// format string or one of its rendered arguments.
+///
+/// # Example
+///
+/// ```
+/// use rustler_logger::*;
+///
+/// let _capture = CaptureScope::new();
+///
+/// Log::new(LogLevel::Warning, "disk usage at ~s%")
+///     .arg("93")
+///     .send();
+///
+/// assert_logged(LogLevel::Warning, "93");
+/// ```
+pub fn assert_logged(level: LogLevel, substring: &str) {
+    let logs = captured_logs();
+    let found = logs.iter().any(|log| {
+        log.level == level
+            && (log.format.contains(substring)
+                || log.args.iter().any(|arg| arg.contains(substring)))
+    });
+    assert!(
+        found,
+        "expected a {level:?} log containing {substring:?}, but captured: {logs:#?}"
+    );
+}
+
+/// Clears the capture buffer on construction, so tests don't leak captured
+/// logs into one another. Typically bound at the top of a test:
+///
+/// ```
+/// use rustler_logger::*;
+///
+/// let _capture = CaptureScope::new();
+/// ```
+pub struct CaptureScope {
+    _private: (),
+}
+
+impl CaptureScope {
+    /// Clear the capture buffer and return a new scope guard.
+    pub fn new() -> Self {
+        clear_captured();
+        CaptureScope { _private: () }
+    }
+}
+
+impl Default for CaptureScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::set_global_level;
+
+    // `Log::send`'s testing path filters on the global level, which is
+    // process-wide state shared with `filter`'s own tests; pin it back to
+    // `Debug` so a transient override left by another test can't filter out
+    // messages these tests expect to see.
+    fn reset() -> CaptureScope {
+        set_global_level(LogLevel::Debug);
+        CaptureScope::new()
+    }
+
+    #[test]
+    fn test_captured_logs_records_level_format_and_args() {
+        let _capture = reset();
+        Log::new(LogLevel::Warning, "disk usage at ~s%")
+            .arg("93")
+            .send();
+
+        let logs = captured_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].level, LogLevel::Warning);
+        assert_eq!(logs[0].format, "disk usage at ~s%");
+        assert!(logs[0].args[0].contains("93"));
+    }
+
+    #[test]
+    fn test_clear_captured_empties_the_buffer() {
+        let _capture = reset();
+        Log::new(LogLevel::Info, "noise").send();
+        assert_eq!(captured_logs().len(), 1);
+
+        clear_captured();
+        assert!(captured_logs().is_empty());
+    }
+
+    #[test]
+    fn test_capture_scope_clears_buffer_on_construction() {
+        set_global_level(LogLevel::Debug);
+        Log::new(LogLevel::Info, "leftover").send();
+        assert_eq!(captured_logs().len(), 1);
+
+        let _capture = CaptureScope::new();
+        assert!(captured_logs().is_empty());
+    }
+
+    #[test]
+    fn test_assert_logged_finds_a_matching_message() {
+        let _capture = reset();
+        Log::new(LogLevel::Error, "connection to ~s failed")
+            .arg("db1")
+            .send();
+
+        assert_logged(LogLevel::Error, "db1");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a Critical log")]
+    fn test_assert_logged_panics_when_nothing_matches() {
+        let _capture = reset();
+        Log::new(LogLevel::Info, "all fine").send();
+
+        assert_logged(LogLevel::Critical, "fine");
+    }
+}
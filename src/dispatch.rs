@@ -0,0 +1,160 @@
+//! Non-blocking, buffered delivery of log messages off the calling thread.
+//!
+//! Enabled via [`log_init_with_async`]. Once enabled, `Log::send` becomes a
+//! cheap push onto a bounded channel instead of a synchronous `env.send`, so
+//! a slow or backed-up `logger_proxy` mailbox can no longer stall the
+//! calling NIF or block a BEAM scheduler thread. A dedicated background
+//! thread owns an [`OwnedEnv`] and drains the channel, shipping messages to
+//! Elixir (or `stderr`, via the same fallback `Log::send` uses) on its own
+//! time.
+use super::atoms;
+use super::message::Log;
+use rustler::{Atom, Encoder, Env, OwnedEnv, Term};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the dispatcher thread checks for, and reports, dropped messages.
+const DROP_WARNING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The channel the dispatcher thread drains. `None` until
+/// [`log_init_with_async`] is called, which is how `Log::send` tells
+/// whether async delivery is enabled at all.
+static QUEUE: OnceLock<SyncSender<Log>> = OnceLock::new();
+
+/// Count of messages dropped because the queue was full, since the last
+/// warning was emitted.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Enable asynchronous log delivery.
+///
+/// Spawns a background thread that owns a bounded channel of depth
+/// `queue_bound` (at least 1) and an [`OwnedEnv`] used to ship messages to
+/// the `logger_proxy` process. When the queue is full, the new message is
+/// dropped (rather than blocking the caller or evicting an older message,
+/// which the channel types here don't support doing cheaply) and counted;
+/// a "N messages dropped" warning is logged to `stderr` at most once every
+/// five seconds so a stuck consumer can't silently lose messages forever,
+/// or exhaust memory queuing them.
+///
+/// Also performs whatever [`log_init`](super::log_init) does (registering
+/// the panic hook). Only the first call in a process spawns the
+/// dispatcher thread; later calls are no-ops.
+///
+/// # Example
+///
+/// ```ignore
+/// fn load(_env: Env, _term: Term) -> bool {
+///     rustler_logger::log_init_with_async(1024);
+///     true
+/// }
+/// ```
+pub fn log_init_with_async(queue_bound: usize) {
+    super::log_init();
+
+    if QUEUE.get().is_some() {
+        return;
+    }
+
+    let (sender, receiver) = sync_channel(queue_bound.max(1));
+    if QUEUE.set(sender).is_ok() {
+        thread::Builder::new()
+            .name("rustler_logger-dispatch".to_string())
+            .spawn(move || run_dispatcher(receiver))
+            .expect("failed to spawn rustler_logger async dispatch thread");
+    }
+}
+
+/// Whether [`log_init_with_async`] has been called in this process.
+///
+/// Only consulted from `Log::send`'s non-`testing` implementation, which is
+/// itself compiled out under `cfg(test)`/`feature = "testing"` — so this is
+/// gated the same way to avoid it being flagged as dead code there.
+#[cfg(not(any(doctest, test, feature = "testing")))]
+pub(crate) fn is_enabled() -> bool {
+    QUEUE.get().is_some()
+}
+
+/// Queue `log` for asynchronous delivery. `log.pending` must already be
+/// `false` (the caller is expected to have done the bookkeeping `Log::send`
+/// normally does) so a dropped message doesn't trigger `Log`'s
+/// drop-without-sending panic.
+#[cfg(not(any(doctest, test, feature = "testing")))]
+pub(crate) fn enqueue(log: Log) {
+    if let Some(queue) = QUEUE.get() {
+        if queue.try_send(log).is_err() {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Body of the background dispatch thread: drain the queue, deliver each
+/// message, and periodically report anything that got dropped.
+fn run_dispatcher(receiver: Receiver<Log>) {
+    let mut env = OwnedEnv::new();
+    let mut last_warning = Instant::now();
+
+    loop {
+        match receiver.recv_timeout(DROP_WARNING_INTERVAL) {
+            Ok(log) => deliver(&mut env, log),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if last_warning.elapsed() >= DROP_WARNING_INTERVAL {
+            let dropped = DROPPED.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                eprintln!(
+                    "[rustler_logger] dropped {dropped} log message(s): async delivery queue was full"
+                );
+            }
+            last_warning = Instant::now();
+        }
+    }
+}
+
+/// Deliver a single message using `env`, falling back to `stderr` if the
+/// `logger_proxy` process isn't registered or alive.
+fn deliver(env: &mut OwnedEnv, log: Log) {
+    let proxy_pid = env.run(|e| {
+        e.whereis_pid(atoms::logger_proxy())
+            .filter(|pid| pid.is_alive(e))
+    });
+
+    match proxy_pid {
+        Some(pid) => {
+            if env.send_and_clear(&pid, |e| encode_log(&log, e)).is_err() {
+                eprintln!("{}", log.render_for_stderr());
+            }
+        }
+        None => eprintln!("{}", log.render_for_stderr()),
+    }
+}
+
+/// Encode `log` the same way the synchronous `Log::send` path does.
+fn encode_log<'a>(log: &Log, env: Env<'a>) -> Term<'a> {
+    let args: Vec<Term> = log.args.iter().map(|arg| arg.encode(env)).collect();
+    let metadata_pairs: Vec<(Term, Term)> = log
+        .metadata
+        .iter()
+        .map(|(key, value)| {
+            (
+                Atom::from_str(env, key).unwrap().to_term(env),
+                value.encode(env),
+            )
+        })
+        .collect();
+    let metadata =
+        Term::map_from_pairs(env, &metadata_pairs[..]).expect("Failed to create metadata map");
+
+    (
+        atoms::log(),
+        log.level.as_atom(),
+        log.format.clone(),
+        args,
+        metadata,
+    )
+        .encode(env)
+}
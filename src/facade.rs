@@ -0,0 +1,145 @@
+//! Bridges the standard [`log`] crate facade into Elixir's Logger.
+//!
+//! Many dependencies emit through `log::debug!`/`log::info!`/etc. rather than
+//! this crate's own `log!`/[`Log`](super::Log) API, so without this bridge
+//! their output never reaches Elixir. Installing [`ElixirLogFacade`] via
+//! [`log_init_with_facade`] routes every `log::Record` through the same
+//! `Log` machinery used elsewhere in this crate.
+use super::level::LogLevel;
+use super::message::Log;
+
+/// A [`log::Log`] implementation that forwards records to Elixir's Logger.
+///
+/// `log::Record`s can be emitted from any thread, including ones with no
+/// BEAM `Env` active (this crate's `Log::send` already falls back to
+/// `stderr` in that case), so this never panics on the caller's behalf.
+struct ElixirLogFacade;
+
+impl log::Log for ElixirLogFacade {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut log = Log::new(level_to_log_level(record.level()), "~s")
+            .arg(record.args().to_string())
+            .meta_target(record.target());
+
+        if let Some(module_path) = record.module_path() {
+            log = log.meta("module_path", module_path.to_string());
+        }
+        if let Some(file) = record.file() {
+            log = log.meta("file", file.to_string());
+        }
+        if let Some(line) = record.line() {
+            log = log.meta("line", line.to_string());
+        }
+
+        log.send();
+    }
+
+    fn flush(&self) {}
+}
+
+/// Map a `log::Level` onto this crate's [`LogLevel`].
+///
+/// `log` has no equivalent of `Notice`/`Critical`/`Alert`/`Emergency`, so
+/// only the five standard levels are reachable here.
+fn level_to_log_level(level: log::Level) -> LogLevel {
+    match level {
+        log::Level::Error => LogLevel::Error,
+        log::Level::Warn => LogLevel::Warning,
+        log::Level::Info => LogLevel::Info,
+        log::Level::Debug => LogLevel::Debug,
+        log::Level::Trace => LogLevel::Debug, // convenience synonym, as in `LogLevel::from("trace")`
+    }
+}
+
+/// Initialize Elixir logging and bridge the standard `log` crate facade.
+///
+/// In addition to everything [`log_init`](super::log_init) does, this
+/// installs an [`ElixirLogFacade`] as the global `log` logger via
+/// `log::set_boxed_logger`, so third-party crates using `log::debug!` and
+/// friends are routed to Elixir's Logger too. Only the first call in a
+/// process wins; later calls are no-ops, matching `log::set_boxed_logger`'s
+/// own "first one wins" contract.
+///
+/// # Example
+///
+/// ```ignore
+/// fn load(_env: Env, _term: Term) -> bool {
+///     rustler_logger::log_init_with_facade(log::LevelFilter::Debug);
+///     true
+/// }
+/// ```
+pub fn log_init_with_facade(max_level: log::LevelFilter) {
+    super::log_init();
+    if log::set_boxed_logger(Box::new(ElixirLogFacade)).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{captured_logs, clear_captured};
+    use crate::set_global_level;
+    use log::Log as _;
+
+    // `Log::send`'s testing path filters on the global level, which is
+    // process-wide state shared with `filter`'s own tests; pin it back to
+    // `Debug` so a transient override left by another test can't filter out
+    // messages these tests expect to see.
+    fn reset() {
+        set_global_level(LogLevel::Debug);
+        clear_captured();
+    }
+
+    fn meta<'a>(log: &'a crate::CapturedLog, key: &str) -> &'a str {
+        &log.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .unwrap_or_else(|| panic!("expected metadata key {key:?}, got: {:?}", log.metadata))
+            .1
+    }
+
+    #[test]
+    fn test_log_forwards_level_args_and_metadata() {
+        reset();
+
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .target("my_app::pool")
+            .module_path(Some("my_app::pool"))
+            .file(Some("src/pool.rs"))
+            .line(Some(42))
+            .args(format_args!("connection {} dropped", 7))
+            .build();
+
+        ElixirLogFacade.log(&record);
+
+        let logs = captured_logs();
+        assert_eq!(logs.len(), 1);
+        let log = &logs[0];
+        assert_eq!(log.level, LogLevel::Warning);
+        assert_eq!(log.format, "~s");
+        assert!(log.args[0].contains("connection 7 dropped"));
+        assert!(meta(log, "target").contains("my_app::pool"));
+        assert!(meta(log, "module_path").contains("my_app::pool"));
+        assert!(meta(log, "file").contains("src/pool.rs"));
+        assert!(meta(log, "line").contains("42"));
+    }
+
+    #[test]
+    fn test_level_to_log_level_maps_the_five_standard_levels() {
+        assert_eq!(level_to_log_level(log::Level::Error), LogLevel::Error);
+        assert_eq!(level_to_log_level(log::Level::Warn), LogLevel::Warning);
+        assert_eq!(level_to_log_level(log::Level::Info), LogLevel::Info);
+        assert_eq!(level_to_log_level(log::Level::Debug), LogLevel::Debug);
+        assert_eq!(level_to_log_level(log::Level::Trace), LogLevel::Debug);
+    }
+}
@@ -0,0 +1,94 @@
+//! Runtime level filtering, so `Log::send` can cheaply drop messages below a
+//! threshold without round-tripping them to Elixir just to be discarded.
+use super::level::LogLevel;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{LazyLock, RwLock};
+
+/// The global minimum level. Defaults to `Debug`, i.e. no filtering.
+static GLOBAL_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Debug as u8);
+
+/// Per-target overrides, keyed by the message's `target` metadata.
+static TARGET_LEVELS: LazyLock<RwLock<HashMap<String, LogLevel>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Set the global minimum level. Messages below this level are dropped
+/// before they're turned into Erlang terms or sent to the proxy, unless a
+/// [`set_target_level`] override for their target says otherwise.
+pub fn set_global_level(level: LogLevel) {
+    GLOBAL_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Set a minimum level override for a specific target (as tagged via
+/// `Log::meta_target`, e.g. a module path or the `log` crate's
+/// `Record::target()`). Overrides the global level for messages carrying
+/// this target, whichever way it goes.
+pub fn set_target_level(target: &str, level: LogLevel) {
+    TARGET_LEVELS
+        .write()
+        .unwrap()
+        .insert(target.to_string(), level);
+}
+
+/// Returns the effective minimum level for `target`, falling back to the
+/// global level when there's no target-specific override.
+pub(crate) fn effective_level(target: Option<&str>) -> LogLevel {
+    if let Some(target) = target {
+        if let Some(level) = TARGET_LEVELS.read().unwrap().get(target) {
+            return *level;
+        }
+    }
+    LogLevel::from_u8(GLOBAL_LEVEL.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // `set_global_level` mutates process-wide state shared by every test in
+    // this binary, so serialize the tests that touch it; per-target tests
+    // below use their own unique target strings instead, so they don't need
+    // the lock.
+    static GLOBAL_LEVEL_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_effective_level_defaults_to_global_level() {
+        let _guard = GLOBAL_LEVEL_TEST_LOCK.lock().unwrap();
+        set_global_level(LogLevel::Debug);
+        assert_eq!(effective_level(None), LogLevel::Debug);
+        assert_eq!(
+            effective_level(Some("some_target_with_no_override")),
+            LogLevel::Debug
+        );
+    }
+
+    #[test]
+    fn test_set_global_level_changes_the_default() {
+        let _guard = GLOBAL_LEVEL_TEST_LOCK.lock().unwrap();
+        set_global_level(LogLevel::Warning);
+        assert_eq!(effective_level(None), LogLevel::Warning);
+        set_global_level(LogLevel::Debug); // restore the default for other tests
+    }
+
+    #[test]
+    fn test_set_target_level_overrides_global_level() {
+        set_target_level("filter::tests::noisy_target", LogLevel::Error);
+        assert_eq!(
+            effective_level(Some("filter::tests::noisy_target")),
+            LogLevel::Error
+        );
+    }
+
+    #[test]
+    fn test_target_without_override_falls_back_to_global() {
+        let _guard = GLOBAL_LEVEL_TEST_LOCK.lock().unwrap();
+        set_global_level(LogLevel::Info);
+        set_target_level("filter::tests::specific_target", LogLevel::Critical);
+        assert_eq!(
+            effective_level(Some("filter::tests::unrelated_target")),
+            LogLevel::Info
+        );
+        set_global_level(LogLevel::Debug); // restore the default for other tests
+    }
+}
@@ -72,6 +72,21 @@ impl LogLevel {
             LogLevel::Emergency => atoms::emergency(),
         }
     }
+
+    /// Reconstructs a `LogLevel` from the `u8` produced by casting with
+    /// `as u8`. Used to stash the current level filter in an `AtomicU8`.
+    pub(crate) fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LogLevel::Debug,
+            1 => LogLevel::Info,
+            2 => LogLevel::Notice,
+            3 => LogLevel::Warning,
+            4 => LogLevel::Error,
+            5 => LogLevel::Critical,
+            6 => LogLevel::Alert,
+            _ => LogLevel::Emergency,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -11,6 +11,13 @@
 //! - the `log_to_elixir` attribute that provides logging functionality in nifs
 //! - the `log!` macro to actually emit log messages explicitly
 //! - a panic handler that routes panic message to Elixir
+//! - (with the `log-facade` feature) a bridge that routes the standard
+//!   `log` crate's `debug!`/`info!`/etc. through to Elixir as well
+//! - an optional non-blocking async delivery mode for log messages
+//! - (with the `testing` feature, or under `cfg(test)`) an in-memory capture
+//!   sink for asserting on logged messages without a live BEAM
+//! - global and per-target runtime level filtering, via `set_global_level`
+//!   and `set_target_level`/`Log::meta_target`
 //!
 //! # Usage
 //!
@@ -60,9 +67,19 @@
 
 /// Pre-allocated atoms for use logging.
 mod atoms;
+/// In-memory capture of log messages sent during tests.
+#[cfg(any(doctest, test, feature = "testing"))]
+mod capture;
 /// Infrastructure to provide context tracking to allow logging from anywhere
 /// within a NIF thread.
 mod context;
+/// Non-blocking, buffered delivery of log messages off the NIF thread.
+mod dispatch;
+/// Bridge from the standard `log` crate facade into Elixir's Logger.
+#[cfg(feature = "log-facade")]
+mod facade;
+/// Per-target and global runtime level filtering.
+mod filter;
 /// Enums for indicating log levels.
 mod level;
 /// Message formatting and sending.
@@ -71,8 +88,15 @@ mod message;
 mod panic;
 
 // provide API
+#[cfg(any(doctest, test, feature = "testing"))]
+pub use capture::{assert_logged, captured_logs, clear_captured, CaptureScope, CapturedLog};
+pub use dispatch::log_init_with_async;
+#[cfg(feature = "log-facade")]
+pub use facade::log_init_with_facade;
+pub use filter::{set_global_level, set_target_level};
 pub use level::*;
 pub use message::*;
+pub use panic::set_max_backtrace_len;
 
 // include convenience macros
 pub use rustler_logger_macro::*;
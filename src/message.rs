@@ -1,8 +1,8 @@
 // Contains the main API to send log messages.
 use super::level::LogLevel;
-use rustler::Encoder;
-use std::panic::set_hook;
-use std::rc::Rc;
+use rustler::{Atom, Encoder, Env, Term};
+use std::panic::{set_hook, take_hook};
+use std::sync::Arc;
 use std::sync::LazyLock;
 
 /// A structure to represent log messages.
@@ -13,13 +13,31 @@ pub struct Log {
     /// format string for the simple message, uses Erlang formatter syntax
     pub format: String,
     /// arguments for the format string
-    pub args: Vec<Rc<dyn Encoder>>,
+    pub args: Vec<Arc<dyn Encoder + Send + Sync>>,
     /// metadata for the log message
-    pub metadata: Vec<(String, Rc<dyn Encoder>)>,
+    pub metadata: Vec<(String, Arc<dyn Encoder + Send + Sync>)>,
+    /// cached copy of the `target` metadata value (if any), set via
+    /// `meta_target`, so `send` can look up its filter threshold without
+    /// decoding the `metadata` entries.
+    target: Option<String>,
     /// used to catch unsent messages that accidentally get dropped
     pub pending: bool,
 }
 
+/// If `value` is a `&str` or `String`, return it as an owned `String`;
+/// otherwise `None`. Used by [`Log::meta`] to recognize a `"target"` value
+/// it can cache for filtering, without requiring every metadata value to be
+/// stringly-typed.
+fn target_metadata_value<T: 'static>(value: &T) -> Option<String> {
+    use std::any::Any;
+
+    if let Some(s) = (value as &dyn Any).downcast_ref::<&str>() {
+        Some((*s).to_string())
+    } else {
+        (value as &dyn Any).downcast_ref::<String>().cloned()
+    }
+}
+
 impl Log {
     /// Create a new Log builder.
     ///
@@ -46,6 +64,7 @@ impl Log {
             format: format.to_string(),
             args: Vec::new(),
             metadata: Vec::new(),
+            target: None,
             pending: true,
         }
     }
@@ -61,8 +80,8 @@ impl Log {
     ///     .arg("world")
     ///     .send();
     /// ```
-    pub fn arg(mut self, arg: impl Encoder + 'static) -> Self {
-        self.args.push(Rc::new(arg));
+    pub fn arg(mut self, arg: impl Encoder + Send + Sync + 'static) -> Self {
+        self.args.push(Arc::new(arg));
         self
     }
 
@@ -81,9 +100,9 @@ impl Log {
     ///     .opt_arg(Some("world"))
     ///     .send();
     /// ```
-    pub fn opt_arg(mut self, arg: Option<impl Encoder + 'static>) -> Self {
+    pub fn opt_arg(mut self, arg: Option<impl Encoder + Send + Sync + 'static>) -> Self {
         if let Some(arg) = arg {
-            self.args.push(Rc::new(arg));
+            self.args.push(Arc::new(arg));
         }
         self
     }
@@ -102,19 +121,25 @@ impl Log {
     /// ```
     pub fn opt_arg_else(
         mut self,
-        some_arg: Option<impl Encoder + 'static>,
-        none_arg: impl Encoder + 'static,
+        some_arg: Option<impl Encoder + Send + Sync + 'static>,
+        none_arg: impl Encoder + Send + Sync + 'static,
     ) -> Self {
         if let Some(arg) = some_arg {
-            self.args.push(Rc::new(arg));
+            self.args.push(Arc::new(arg));
         } else {
-            self.args.push(Rc::new(none_arg));
+            self.args.push(Arc::new(none_arg));
         }
         self
     }
 
     /// Builder-style method to put a key-value-pair into a log message.
     ///
+    /// A `"target"` key is special-cased: if `value` is a `&str` or `String`,
+    /// it's also cached the same way [`meta_target`](Log::meta_target) does,
+    /// so `.meta("target", "my_app::pool")` and `.meta_target("my_app::pool")`
+    /// are both picked up by [`set_target_level`](crate::set_target_level)
+    /// instead of the former silently bypassing filtering.
+    ///
     /// # Example
     ///
     /// ```
@@ -125,8 +150,11 @@ impl Log {
     ///     .meta("user_id", 123)
     ///     .send();
     /// ```
-    pub fn meta(mut self, key: &str, value: impl Encoder + 'static) -> Self {
-        self.metadata.push((key.to_string(), Rc::new(value)));
+    pub fn meta(mut self, key: &str, value: impl Encoder + Send + Sync + 'static) -> Self {
+        if key == "target" {
+            self.target = target_metadata_value(&value);
+        }
+        self.metadata.push((key.to_string(), Arc::new(value)));
         self
     }
     /// Builder-style method to put an optional key-value-pair into a log
@@ -148,15 +176,72 @@ impl Log {
     ///     .opt_meta("uid", uid)
     ///     .send();
     /// ```
-    pub fn opt_meta(mut self, key: &str, value: Option<impl Encoder + 'static>) -> Self {
+    pub fn opt_meta(
+        mut self,
+        key: &str,
+        value: Option<impl Encoder + Send + Sync + 'static>,
+    ) -> Self {
         if let Some(value) = value {
-            self.metadata.push((key.to_string(), Rc::new(value)));
+            self.metadata.push((key.to_string(), Arc::new(value)));
         }
         self
     }
 
+    /// Builder-style method to tag this message with a filtering target.
+    ///
+    /// This is sugar for `.meta("target", target)`, which caches the target
+    /// so `send` can cheaply look up its effective level via
+    /// [`set_global_level`](crate::set_global_level) /
+    /// [`set_target_level`](crate::set_target_level) without decoding the
+    /// `metadata` entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rustler_logger::*;
+    ///
+    /// let log = Log::new(LogLevel::Debug, "connection established")
+    ///     .meta_target("my_app::pool")
+    ///     .send();
+    /// ```
+    pub fn meta_target(self, target: &str) -> Self {
+        self.meta("target", target.to_string())
+    }
+
+    /// Builder-style method to put a nested, grouped key-value-pair into a
+    /// log message.
+    ///
+    /// Where `meta` only accepts a flat value, `meta_group` hands the
+    /// closure a fresh [`MetaBuilder`] to accumulate sub-fields into; the
+    /// group is encoded as a nested map under `key` when `send` runs.
+    /// Groups can nest arbitrarily by calling `meta_group` again inside the
+    /// closure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rustler_logger::*;
+    ///
+    /// let log = Log::new(LogLevel::Info, "Hello, {}!")
+    ///     .arg("world")
+    ///     .meta_group("request", |req| {
+    ///         req.meta("id", 42).meta("path", "/hello")
+    ///     })
+    ///     .send();
+    /// ```
+    pub fn meta_group(mut self, key: &str, build: impl FnOnce(MetaBuilder) -> MetaBuilder) -> Self {
+        let group = build(MetaBuilder::new());
+        self.metadata.push((key.to_string(), Arc::new(group)));
+        self
+    }
+
     /// Sends the constructed log message.
     ///
+    /// If the BEAM `Env` isn't in scope on this thread, the `logger_proxy`
+    /// process isn't registered or alive, or delivery otherwise fails, the
+    /// message is written to `stderr` instead. Logging is best-effort and
+    /// must never be the reason the node goes down.
+    ///
     /// # Example
     ///
     /// ```
@@ -171,14 +256,39 @@ impl Log {
     pub fn send(mut self) {
         use super::atoms;
         use super::context::ENV;
-        use rustler::{Atom, Term};
+        use super::dispatch;
 
         if !self.pending {
             panic!("attempt to send a log message that has already been used")
         }
 
+        if self.below_threshold() {
+            self.pending = false;
+            return;
+        }
+        self.pending = false;
+
+        if dispatch::is_enabled() {
+            dispatch::enqueue(self);
+            return;
+        }
+
+        if !ENV.is_set() {
+            eprintln!("{}", self.render_for_stderr());
+            return;
+        }
+
         ENV.with(|env_ptr| {
             let env = *env_ptr;
+
+            let logger_proxy_pid = match env.whereis_pid(atoms::logger_proxy()) {
+                Some(pid) if pid.is_alive(env) => pid,
+                _ => {
+                    eprintln!("{}", self.render_for_stderr());
+                    return;
+                }
+            };
+
             let args: Vec<Term> = self.args.iter().map(|arg| arg.encode(env)).collect();
             let metadata_pairs: Vec<(Term, Term)> = self
                 .metadata
@@ -195,15 +305,6 @@ impl Log {
                 Ok(map) => map,
                 Err(_) => panic!("Failed to create metadata map"),
             };
-            let logger_proxy_pid = match env.whereis_pid(atoms::logger_proxy()) {
-                Some(pid) => pid,
-                None => panic!("BEAM logger proxy process is not registered?!"),
-            };
-            if !logger_proxy_pid.is_alive(env) {
-                panic!("BEAM logger proxy process is not alive?!");
-            }
-
-            self.pending = false;
 
             let log_msg = (
                 atoms::log(),
@@ -214,20 +315,60 @@ impl Log {
             );
 
             if env.send(&logger_proxy_pid, log_msg).is_err() {
-                panic!("failed to ship log message to Elixir");
-            };
+                eprintln!("{}", self.render_for_stderr());
+            }
         });
     }
 
+    /// Render this message as a plain-text line for the `stderr` fallback
+    /// path, used when the BEAM logger proxy can't be reached. Also used by
+    /// the async dispatcher, for the same reason, so this isn't gated to the
+    /// non-`testing` build like `send` is.
+    pub(crate) fn render_for_stderr(&self) -> String {
+        use rustler::OwnedEnv;
+
+        let level: &str = self.level.into();
+        let (args, metadata) = OwnedEnv::new().run(|env| {
+            let args: Vec<String> = self
+                .args
+                .iter()
+                .map(|arg| format!("{:?}", arg.encode(env)))
+                .collect();
+            let metadata: Vec<String> = self
+                .metadata
+                .iter()
+                .map(|(key, value)| format!("{key}={:?}", value.encode(env)))
+                .collect();
+            (args, metadata)
+        });
+
+        format!(
+            "[rustler_logger] ({level}) {} args={args:?} metadata={{{}}}",
+            self.format,
+            metadata.join(", ")
+        )
+    }
+
     // Don't actually try to send in tests. It won't work because we don't have
     // a real `Env`.
     #[cfg(any(doctest, test, feature = "testing"))]
     pub fn send(mut self) {
         if !self.pending {
             panic!("attempt to send a log message that has already been used")
-        } else {
-            self.pending = false;
         }
+
+        if !self.below_threshold() {
+            super::capture::capture(&self);
+        }
+        self.pending = false;
+    }
+
+    /// Whether this message's level is below the effective threshold for its
+    /// target (or the global threshold, if it has none), per
+    /// [`set_global_level`](crate::set_global_level) /
+    /// [`set_target_level`](crate::set_target_level).
+    fn below_threshold(&self) -> bool {
+        self.level < super::filter::effective_level(self.target.as_deref())
     }
 
     /// Cancel a log message that has not been sent yet.
@@ -265,9 +406,59 @@ impl Drop for Log {
     }
 }
 
+/// Accumulates key-value pairs for a nested metadata group, built up inside
+/// the closure passed to [`Log::meta_group`]. Encodes to a nested map when
+/// the enclosing message is sent.
+pub struct MetaBuilder {
+    pairs: Vec<(String, Arc<dyn Encoder + Send + Sync>)>,
+}
+
+impl MetaBuilder {
+    fn new() -> Self {
+        MetaBuilder { pairs: Vec::new() }
+    }
+
+    /// Builder-style method to put a key-value-pair into this group.
+    pub fn meta(mut self, key: &str, value: impl Encoder + Send + Sync + 'static) -> Self {
+        self.pairs.push((key.to_string(), Arc::new(value)));
+        self
+    }
+
+    /// Builder-style method to nest another group under `key` within this
+    /// one, recursing arbitrarily deep.
+    pub fn meta_group(mut self, key: &str, build: impl FnOnce(MetaBuilder) -> MetaBuilder) -> Self {
+        let group = build(MetaBuilder::new());
+        self.pairs.push((key.to_string(), Arc::new(group)));
+        self
+    }
+}
+
+impl Encoder for MetaBuilder {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let pairs: Vec<(Term, Term)> = self
+            .pairs
+            .iter()
+            .map(|(key, value)| {
+                (
+                    Atom::from_str(env, key).unwrap().to_term(env),
+                    value.encode(env),
+                )
+            })
+            .collect();
+        Term::map_from_pairs(env, &pairs[..]).expect("Failed to create nested metadata map")
+    }
+}
+
 static INITIALIZED: LazyLock<bool> = LazyLock::new(|| {
-    // set the panic hook
-    set_hook(Box::new(super::panic::panic_hook));
+    // Chain to whatever hook was already registered (e.g. the default Rust
+    // hook, or one installed by another library) instead of clobbering it.
+    // Our hook runs first so PANIC_INFO is populated before the prior hook
+    // gets a chance to print or otherwise consume the panic.
+    let previous_hook = take_hook();
+    set_hook(Box::new(move |info| {
+        super::panic::panic_hook(info);
+        previous_hook(info);
+    }));
     true
 });
 
@@ -290,3 +481,49 @@ static INITIALIZED: LazyLock<bool> = LazyLock::new(|| {
 pub fn log_init() {
     assert!(*INITIALIZED);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{captured_logs, clear_captured};
+    use crate::set_global_level;
+
+    // `Log::send`'s testing path filters on the global level, which is
+    // process-wide state shared with `filter`'s own tests; pin it back to
+    // `Debug` so a transient override left by another test can't filter out
+    // messages these tests expect to see.
+    fn reset() {
+        set_global_level(LogLevel::Debug);
+        clear_captured();
+    }
+
+    #[test]
+    fn test_meta_group_encodes_a_nested_map() {
+        reset();
+        Log::new(LogLevel::Info, "request handled")
+            .meta_group("request", |r| r.meta("id", 42).meta("path", "/hello"))
+            .send();
+
+        let logs = captured_logs();
+        assert_eq!(logs.len(), 1);
+        let (key, rendered) = &logs[0].metadata[0];
+        assert_eq!(key, "request");
+        assert!(rendered.contains("42"));
+        assert!(rendered.contains("/hello"));
+    }
+
+    #[test]
+    fn test_meta_group_can_nest_groups() {
+        reset();
+        Log::new(LogLevel::Info, "nested")
+            .meta_group("outer", |o| o.meta_group("inner", |i| i.meta("value", "x")))
+            .send();
+
+        let logs = captured_logs();
+        assert_eq!(logs.len(), 1);
+        let (key, rendered) = &logs[0].metadata[0];
+        assert_eq!(key, "outer");
+        assert!(rendered.contains("inner"));
+        assert!(rendered.contains('x'));
+    }
+}
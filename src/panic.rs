@@ -1,7 +1,53 @@
 // Panic Handling / Integration
 use super::level::LogLevel;
 use super::message::Log;
-use std::{any::Any, cell::RefCell, panic::PanicHookInfo};
+use std::{
+    any::Any,
+    backtrace::{Backtrace, BacktraceStatus},
+    cell::RefCell,
+    panic::PanicHookInfo,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Default maximum length, in bytes, of a captured backtrace before it is
+/// truncated. Backtraces can easily run to tens of kilobytes, which is more
+/// than we want to ship to Elixir in a single log message.
+const DEFAULT_MAX_BACKTRACE_LEN: usize = 8 * 1024;
+
+/// Configurable cap on backtrace length, in bytes. Adjust with
+/// [`set_max_backtrace_len`].
+static MAX_BACKTRACE_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_BACKTRACE_LEN);
+
+/// Set the maximum length, in bytes, that a captured backtrace will be
+/// truncated to before it is attached to a panic report.
+///
+/// Defaults to 8 KiB.
+pub fn set_max_backtrace_len(len: usize) {
+    MAX_BACKTRACE_LEN.store(len, Ordering::Relaxed);
+}
+
+/// Truncate `backtrace` to the configured maximum length, on a char
+/// boundary, appending a marker if anything was cut off.
+fn truncate_backtrace(backtrace: String) -> String {
+    truncate_to_len(backtrace, MAX_BACKTRACE_LEN.load(Ordering::Relaxed))
+}
+
+/// Truncate `backtrace` to at most `max` bytes, backing off to the nearest
+/// UTF-8 char boundary at or before `max` so a multi-byte codepoint never
+/// gets split, and appending a marker if anything was cut off. Split out
+/// from `truncate_backtrace` so it can be unit-tested without touching the
+/// global `MAX_BACKTRACE_LEN` setting.
+fn truncate_to_len(mut backtrace: String, max: usize) -> String {
+    if backtrace.len() > max {
+        let mut cut = max;
+        while !backtrace.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        backtrace.truncate(cut);
+        backtrace.push_str("... (truncated)");
+    }
+    backtrace
+}
 
 /// A structure to hold panic information.
 pub(crate) struct PanicInfo {
@@ -9,6 +55,9 @@ pub(crate) struct PanicInfo {
     pub(crate) file: Option<String>,
     pub(crate) line: Option<u32>,
     pub(crate) col: Option<u32>,
+    /// captured Rust backtrace, respecting `RUST_BACKTRACE`/`RUST_LIB_BACKTRACE`;
+    /// `None` when backtrace capture is disabled.
+    pub(crate) backtrace: Option<String>,
 }
 
 thread_local! {
@@ -23,11 +72,17 @@ thread_local! {
 pub fn panic_hook(info: &PanicHookInfo) {
     let err = any_to_string(info.payload());
     let loc = info.location();
+    let backtrace = Backtrace::capture();
+    let backtrace = match backtrace.status() {
+        BacktraceStatus::Captured => Some(truncate_backtrace(backtrace.to_string())),
+        _ => None,
+    };
     let panic_info = PanicInfo {
         message: err,
         file: loc.map(|l| l.file().to_string()),
         line: loc.map(|l| l.line()),
         col: loc.map(|l| l.column()),
+        backtrace,
     };
     PANIC_INFO.with(|info| {
         *info.borrow_mut() = Some(panic_info);
@@ -68,11 +123,18 @@ pub fn send_panic_message(fname: &str, arity: u32) {
         Some(PanicInfo { col: Some(col), .. }) => col.to_string(),
         _ => "?".to_string(),
     };
+    let backtrace = panic_info.and_then(|p| p.backtrace.clone());
 
     // We want to use some of the optional argument functionality which isn't
     // available using the quick logging macro, so we construct the message
     // ourselves here.
-    Log::new(LogLevel::Critical, "rustler_nif_panic[~s/~s@~s:~s:~s]: ~s")
+    let format = if backtrace.is_some() {
+        "rustler_nif_panic[~s/~s@~s:~s:~s]: ~s\nbacktrace:\n~s"
+    } else {
+        "rustler_nif_panic[~s/~s@~s:~s:~s]: ~s"
+    };
+
+    let log = Log::new(LogLevel::Critical, format)
         .arg(fname.to_string())
         .arg(arity.to_string())
         .arg(file.clone())
@@ -84,6 +146,41 @@ pub fn send_panic_message(fname: &str, arity: u32) {
         )
         .meta("file", file)
         .meta("line", line)
-        .meta("column", col)
-        .send();
+        .meta("column", col);
+
+    let log = match backtrace {
+        Some(backtrace) => log.meta("backtrace", backtrace.clone()).arg(backtrace),
+        None => log,
+    };
+
+    log.send();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::truncate_to_len;
+
+    #[test]
+    fn test_truncate_to_len_leaves_short_backtrace_untouched() {
+        let backtrace = "a".repeat(32);
+        assert_eq!(truncate_to_len(backtrace.clone(), 64), backtrace);
+    }
+
+    #[test]
+    fn test_truncate_to_len_truncates_and_appends_marker() {
+        let backtrace = "a".repeat(100);
+        let truncated = truncate_to_len(backtrace, 16);
+        assert!(truncated.starts_with(&"a".repeat(16)));
+        assert!(truncated.ends_with("... (truncated)"));
+    }
+
+    #[test]
+    fn test_truncate_to_len_backs_off_to_char_boundary() {
+        // The emoji is 4 bytes starting at byte 4, so cutting at byte 5
+        // would land mid-codepoint; the truncation should back off to the
+        // boundary at byte 4 instead of panicking or splitting it.
+        let backtrace = format!("abcd{}efgh", '\u{1F600}');
+        let truncated = truncate_to_len(backtrace, 5);
+        assert_eq!(truncated, "abcd... (truncated)");
+    }
 }